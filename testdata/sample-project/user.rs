@@ -1,14 +1,105 @@
 // Rust user module
 
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+
+#[derive(Debug, Clone)]
 pub struct User {
-    pub id: i64,
+    pub id: UserId,
     pub name: String,
     pub email: String,
+    pub credential: Option<Credential>,
+    pub role: UserRole,
+    /// Permissions granted to this user beyond whatever `Policy` grants
+    /// their role, e.g. explicit overrides from `users.toml`.
+    pub permission_overrides: HashSet<Permission>,
+}
+
+/// A user identifier that can hold either the legacy numeric id or the
+/// newer, deterministic UUID derived from the username. Repositories still
+/// backed by numeric ids can keep using `Legacy` while new users get
+/// `Stable` ids that are reproducible across runs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UserId {
+    Legacy(i64),
+    Stable(Uuid),
+}
+
+/// Wraps an existing numeric id as a `UserId`, e.g. a row pulled from a
+/// database column that predates the UUID v5 migration.
+impl From<i64> for UserId {
+    fn from(id: i64) -> Self {
+        UserId::Legacy(id)
+    }
+}
+
+/// A salted password hash. The salt is per-user and the hash is derived by
+/// `hash_password`, never stored or compared in plaintext.
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub hash: [u8; 64],
+    pub salt: [u8; 16],
 }
 
 pub trait UserRepository {
-    fn find_by_id(&self, id: i64) -> Option<User>;
+    fn find_by_id(&self, id: &UserId) -> Option<User>;
+    fn find_by_email(&self, email: &str) -> Option<User>;
+    /// Persists `user`. Implementations should treat `user.id` as the
+    /// dedupe key: since `create_user` now derives a stable id from the
+    /// username, the same username always maps to the same id and a
+    /// second `create` for it is detectable as a duplicate.
     fn create(&self, user: &User) -> Result<(), String>;
+    /// Every user known to this repository, used by `search` to rank
+    /// candidates. Implementations backed by a database can page through
+    /// rows to build the returned list. Defaults
+    /// to empty so existing implementors aren't forced to support this
+    /// just to keep compiling; override it to make `search` useful.
+    fn all(&self) -> Vec<User> {
+        Vec::new()
+    }
+
+    /// Ranks every user by trigram similarity of `query` against their
+    /// name and email, taking the greater of the two scores, and returns
+    /// those scoring at or above `min_score` sorted best match first.
+    /// Typo-tolerant stand-in for an exact `find_by_email`/`find_by_id`
+    /// lookup.
+    fn search(&self, query: &str, min_score: f32) -> Vec<(User, f32)> {
+        let query_trigrams = trigrams(query);
+        let mut scored: Vec<(User, f32)> = self
+            .all()
+            .into_iter()
+            .map(|user| {
+                let name_score = trigram_similarity(&query_trigrams, &trigrams(&user.name));
+                let email_score = trigram_similarity(&query_trigrams, &trigrams(&user.email));
+                (user, name_score.max(email_score))
+            })
+            .filter(|(_, score)| *score >= min_score)
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored
+    }
+}
+
+/// Lowercases `s`, pads it with boundary markers, and returns the set of
+/// its 3-character windows (à la `pg_trgm`).
+fn trigrams(s: &str) -> HashSet<String> {
+    let padded = format!("  {}  ", s.to_lowercase());
+    let chars: Vec<char> = padded.chars().collect();
+    chars
+        .windows(3)
+        .map(|w| w.iter().collect())
+        .collect()
+}
+
+/// Jaccard similarity between two trigram sets: `|A ∩ B| / |A ∪ B|`.
+fn trigram_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count();
+    let union = a.union(b).count();
+    intersection as f32 / union as f32
 }
 
 pub struct UserService<R: UserRepository> {
@@ -20,21 +111,1020 @@ impl<R: UserRepository> UserService<R> {
         UserService { repository }
     }
 
-    pub fn get_user(&self, id: i64) -> Option<User> {
+    pub fn get_user(&self, id: &UserId) -> Option<User> {
         self.repository.find_by_id(id)
     }
+
+    /// Looks a user up by a pre-migration numeric id, for callers that
+    /// still hold an `i64` from before ids moved to UUID v5.
+    pub fn get_legacy_user(&self, id: i64) -> Option<User> {
+        self.repository.find_by_id(&UserId::from(id))
+    }
+
+    /// Creates a new user with a salted credential and persists it through
+    /// the repository.
+    pub fn register(&self, name: String, email: String, password: &str) -> Result<User, String> {
+        let salt = generate_salt()?;
+        let hash = hash_password(password, &salt);
+        let id = stable_user_id(&name);
+        let user = User {
+            id,
+            name,
+            email,
+            credential: Some(Credential { hash, salt }),
+            role: UserRole::Member,
+            permission_overrides: HashSet::new(),
+        };
+        self.repository.create(&user)?;
+        Ok(user)
+    }
+
+    /// Looks a user up by email and verifies the supplied password against
+    /// their stored credential, comparing hashes in constant time.
+    pub fn verify_credentials(&self, email: &str, password: &str) -> Result<bool, String> {
+        let user = self
+            .repository
+            .find_by_email(email)
+            .ok_or_else(|| "no such user".to_string())?;
+        let credential = user
+            .credential
+            .as_ref()
+            .ok_or_else(|| "user has no credential".to_string())?;
+        let candidate = hash_password(password, &credential.salt);
+        Ok(constant_time_eq(&candidate, &credential.hash))
+    }
+
+    /// Checks whether `user_id` holds `permission`, either through the
+    /// global role policy or through their `permission_overrides`. Fails
+    /// with `AuthError::NotFound` if the user doesn't exist and
+    /// `AuthError::Denied` if neither their role nor their overrides grant
+    /// the permission.
+    pub fn authorize(&self, user_id: &UserId, permission: Permission) -> Result<(), AuthError> {
+        let user = self
+            .repository
+            .find_by_id(user_id)
+            .ok_or(AuthError::NotFound)?;
+        if Policy::permissions_for(&user.role).contains(&permission)
+            || user.permission_overrides.contains(&permission)
+        {
+            Ok(())
+        } else {
+            Err(AuthError::Denied)
+        }
+    }
+
+    /// Like `authorize`, but also grants access when the user owns the
+    /// resource being acted on (their username matches `resource_owner`),
+    /// mirroring an owner-or-admin rule.
+    pub fn authorize_resource(
+        &self,
+        user_id: &UserId,
+        permission: Permission,
+        resource_owner: &str,
+    ) -> Result<(), AuthError> {
+        let user = self
+            .repository
+            .find_by_id(user_id)
+            .ok_or(AuthError::NotFound)?;
+        if user.name == resource_owner
+            || Policy::permissions_for(&user.role).contains(&permission)
+            || user.permission_overrides.contains(&permission)
+        {
+            Ok(())
+        } else {
+            Err(AuthError::Denied)
+        }
+    }
+
+    /// Reads `path` as a `users.toml` seed file and creates every entry it
+    /// describes through the repository. Validation errors (duplicate
+    /// emails, unknown roles or permissions, missing fields) are
+    /// aggregated across all entries rather than failing on the first one,
+    /// so a caller can fix a whole file's worth of mistakes at once.
+    pub fn seed_from_config(&self, path: &str) -> Result<(), Vec<ConfigError>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| vec![ConfigError::Io(e.to_string())])?;
+        let users = parse_users_toml(&contents)?;
+        for user in users {
+            self.repository
+                .create(&user)
+                .map_err(|e| vec![ConfigError::Create(e)])?;
+        }
+        Ok(())
+    }
 }
 
 pub fn create_user(name: String, email: String) -> User {
-    User { id: 0, name, email }
+    let id = stable_user_id(&name);
+    User {
+        id,
+        name,
+        email,
+        credential: None,
+        role: UserRole::Member,
+        permission_overrides: HashSet::new(),
+    }
+}
+
+/// Derives a reproducible `UserId` from a username: lowercase it, hash it
+/// with SHA-1, and feed that digest into a UUID v5 under `NAMESPACE`. The
+/// same username always yields the same id, so repositories can detect
+/// duplicates without a separate lookup.
+fn stable_user_id(name: &str) -> UserId {
+    let digest = sha1(name.to_lowercase().as_bytes());
+    UserId::Stable(Uuid::new_v5(&NAMESPACE, &digest))
+}
+
+/// Fixed namespace UUID this application derives all user ids from.
+const NAMESPACE: Uuid = Uuid([
+    0x9b, 0x1d, 0xee, 0xb4, 0x8f, 0x57, 0x4e, 0x3a, 0xa2, 0x0c, 0x6d, 0x1b, 0x4a, 0x8e, 0x5f, 0x21,
+]);
+
+/// A UUID (RFC 4122), stored as its raw 16 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    /// Builds a version-5 (SHA-1 name-based) UUID from `namespace` and
+    /// `name`, setting the version and variant bits per RFC 4122.
+    pub fn new_v5(namespace: &Uuid, name: &[u8]) -> Uuid {
+        let mut input = Vec::with_capacity(16 + name.len());
+        input.extend_from_slice(&namespace.0);
+        input.extend_from_slice(name);
+        let digest = sha1(&input);
+
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        bytes[6] = (bytes[6] & 0x0f) | 0x50;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Uuid(bytes)
+    }
+}
+
+/// A repository that can be queried asynchronously, e.g. backed by an
+/// HTTP call or a SQL connection pool rather than an in-memory store.
+///
+/// Method names intentionally mirror `UserRepository`. A type implementing
+/// both needs fully-qualified syntax (`AsyncUserRepository::find_by_id(&r, id)`)
+/// to call these, same as `fetch_user` does below.
+#[allow(async_fn_in_trait)]
+pub trait AsyncUserRepository {
+    async fn find_by_id(&self, id: &UserId) -> Result<User, FetchError>;
+    async fn create(&self, user: &User) -> Result<(), FetchError>;
+}
+
+/// Errors an `AsyncUserRepository` can surface, distinct from the sync
+/// `UserRepository`'s bare `String` so callers can match on the failure
+/// mode instead of parsing a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchError {
+    NotFound,
+    Backend(String),
 }
 
-pub async fn fetch_user(id: i64) -> Result<User, String> {
-    todo!()
+impl<R: UserRepository + AsyncUserRepository> UserService<R> {
+    /// Delegates to the async repository. This is the real implementation
+    /// of what used to be a disconnected `todo!()` free function.
+    pub async fn fetch_user(&self, id: &UserId) -> Result<User, FetchError> {
+        AsyncUserRepository::find_by_id(&self.repository, id).await
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UserRole {
     Admin,
     Member,
     Guest,
 }
+
+/// An action that can be gated behind a `UserRole` via `Policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    CreatePost,
+    Comment,
+    ManageUsers,
+}
+
+/// Errors `UserService::authorize` and `authorize_resource` can return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    NotFound,
+    Denied,
+}
+
+/// Maps each `UserRole` to the set of `Permission`s it's granted.
+pub struct Policy;
+
+impl Policy {
+    pub fn permissions_for(role: &UserRole) -> HashSet<Permission> {
+        match role {
+            UserRole::Admin => {
+                HashSet::from([Permission::CreatePost, Permission::Comment, Permission::ManageUsers])
+            }
+            UserRole::Member => HashSet::from([Permission::CreatePost, Permission::Comment]),
+            UserRole::Guest => HashSet::new(),
+        }
+    }
+}
+
+/// Errors `UserService::seed_from_config` can return, one or more per
+/// malformed `[[user]]` entry, plus `Io` for failing to read the file and `Create`
+/// for a well-formed entry the repository itself rejected (e.g. an id
+/// collision) — kept distinct so callers can tell "bad file" from "bad
+/// entry" apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    MissingField { entry: usize, field: &'static str },
+    UnknownRole { entry: usize, role: String },
+    UnknownPermission { entry: usize, permission: String },
+    DuplicateEmail(String),
+    Io(String),
+    Create(String),
+}
+
+/// Parses a `users.toml` seed file into `User`s. This is a hand-rolled
+/// parser for exactly the `[[user]]` array-of-tables shape this loader
+/// expects (`name`, `email`, optional `role` defaulting to member, optional
+/// `permissions` array of quoted strings) rather than a general-purpose
+/// TOML parser.
+fn parse_users_toml(contents: &str) -> Result<Vec<User>, Vec<ConfigError>> {
+    let mut entries: Vec<Vec<(String, String)>> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "[[user]]" {
+            entries.push(Vec::new());
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if let Some(entry) = entries.last_mut() {
+                entry.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut seen_emails = HashSet::new();
+    let mut users = Vec::new();
+    for (index, fields) in entries.into_iter().enumerate() {
+        match build_seed_user(index, &fields, &mut seen_emails) {
+            Ok(user) => users.push(user),
+            Err(mut entry_errors) => errors.append(&mut entry_errors),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(users)
+    } else {
+        Err(errors)
+    }
+}
+
+fn build_seed_user(
+    index: usize,
+    fields: &[(String, String)],
+    seen_emails: &mut HashSet<String>,
+) -> Result<User, Vec<ConfigError>> {
+    let get = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| unquote(v))
+    };
+
+    let mut errors = Vec::new();
+    let name = get("name");
+    let email = get("email");
+
+    if name.is_none() {
+        errors.push(ConfigError::MissingField { entry: index, field: "name" });
+    }
+    if email.is_none() {
+        errors.push(ConfigError::MissingField { entry: index, field: "email" });
+    }
+
+    let role = match get("role") {
+        Some(raw) => match parse_role(&raw) {
+            Some(role) => Some(role),
+            None => {
+                errors.push(ConfigError::UnknownRole { entry: index, role: raw });
+                None
+            }
+        },
+        None => Some(UserRole::Member),
+    };
+
+    let mut permission_overrides = HashSet::new();
+    if let Some((_, raw)) = fields.iter().find(|(k, _)| k == "permissions") {
+        for token in raw.trim_matches(|c| c == '[' || c == ']').split(',') {
+            let token = unquote(token.trim());
+            if token.is_empty() {
+                continue;
+            }
+            match parse_permission(&token) {
+                Some(permission) => {
+                    permission_overrides.insert(permission);
+                }
+                None => errors.push(ConfigError::UnknownPermission {
+                    entry: index,
+                    permission: token,
+                }),
+            }
+        }
+    }
+
+    let (name, email, role) = match (name, email, role) {
+        (Some(name), Some(email), Some(role)) if errors.is_empty() => (name, email, role),
+        _ => return Err(errors),
+    };
+
+    if !seen_emails.insert(email.clone()) {
+        errors.push(ConfigError::DuplicateEmail(email));
+        return Err(errors);
+    }
+
+    let id = stable_user_id(&name);
+    Ok(User {
+        id,
+        name,
+        email,
+        credential: None,
+        role,
+        permission_overrides,
+    })
+}
+
+fn unquote(raw: &str) -> String {
+    raw.trim().trim_matches('"').to_string()
+}
+
+fn parse_role(raw: &str) -> Option<UserRole> {
+    match raw.to_lowercase().as_str() {
+        "admin" => Some(UserRole::Admin),
+        "member" => Some(UserRole::Member),
+        "guest" => Some(UserRole::Guest),
+        _ => None,
+    }
+}
+
+fn parse_permission(raw: &str) -> Option<Permission> {
+    match raw {
+        "CreatePost" => Some(Permission::CreatePost),
+        "Comment" => Some(Permission::Comment),
+        "ManageUsers" => Some(Permission::ManageUsers),
+        _ => None,
+    }
+}
+
+/// Draws a 16-byte salt from the OS CSPRNG (`/dev/urandom`). Returns an
+/// error rather than panicking so a sandboxed environment without
+/// `/dev/urandom` degrades to an `Err` from `register`, not a crash.
+fn generate_salt() -> Result<[u8; 16], String> {
+    let mut salt = [0u8; 16];
+    let mut urandom =
+        File::open("/dev/urandom").map_err(|e| format!("failed to open CSPRNG source: {e}"))?;
+    urandom
+        .read_exact(&mut salt)
+        .map_err(|e| format!("failed to read random salt: {e}"))?;
+    Ok(salt)
+}
+
+/// Derives a password hash as SHA-512(salt || password). Argon2id would be
+/// preferable but pulls in a dependency this crate doesn't otherwise need;
+/// salted SHA-512 is the fallback called out for this subsystem.
+fn hash_password(password: &str, salt: &[u8; 16]) -> [u8; 64] {
+    let mut input = Vec::with_capacity(salt.len() + password.len());
+    input.extend_from_slice(salt);
+    input.extend_from_slice(password.as_bytes());
+    sha512(&input)
+}
+
+/// Byte-for-byte comparison that always walks the full length, so timing
+/// doesn't leak how many leading bytes matched.
+fn constant_time_eq(a: &[u8; 64], b: &[u8; 64]) -> bool {
+    let mut diff = 0u8;
+    for i in 0..64 {
+        diff |= a[i] ^ b[i];
+    }
+    diff == 0
+}
+
+/// Minimal SHA-1 (FIPS 180-4) over an arbitrary byte slice, used to derive
+/// deterministic UUID v5 user ids.
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let bit_len = (message.len() as u64) * 8;
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&bit_len.to_be_bytes());
+
+    for chunk in padded.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&chunk[i * 4..i * 4 + 4]);
+            w[i] = u32::from_be_bytes(bytes);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let (mut a, mut b, mut c, mut d, mut e) = (h[0], h[1], h[2], h[3], h[4]);
+
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | ((!b) & d), 0x5A827999u32),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        h[0] = h[0].wrapping_add(a);
+        h[1] = h[1].wrapping_add(b);
+        h[2] = h[2].wrapping_add(c);
+        h[3] = h[3].wrapping_add(d);
+        h[4] = h[4].wrapping_add(e);
+    }
+
+    let mut out = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+/// Minimal SHA-512 (FIPS 180-4) over an arbitrary byte slice, used by
+/// `hash_password` so this module has no external hashing dependency.
+fn sha512(message: &[u8]) -> [u8; 64] {
+    let mut h: [u64; 8] = [
+        0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+        0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+    ];
+
+    let bit_len = (message.len() as u128) * 8;
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 128 != 112 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&bit_len.to_be_bytes());
+
+    for chunk in padded.chunks(128) {
+        let mut w = [0u64; 80];
+        for i in 0..16 {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&chunk[i * 8..i * 8 + 8]);
+            w[i] = u64::from_be_bytes(bytes);
+        }
+        for i in 16..80 {
+            let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+            let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh) =
+            (h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7]);
+
+        for i in 0..80 {
+            let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            let ch = (e & f) ^ ((!e) & g);
+            let temp1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA512_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        h[0] = h[0].wrapping_add(a);
+        h[1] = h[1].wrapping_add(b);
+        h[2] = h[2].wrapping_add(c);
+        h[3] = h[3].wrapping_add(d);
+        h[4] = h[4].wrapping_add(e);
+        h[5] = h[5].wrapping_add(f);
+        h[6] = h[6].wrapping_add(g);
+        h[7] = h[7].wrapping_add(hh);
+    }
+
+    let mut out = [0u8; 64];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 8..i * 8 + 8].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Minimal in-memory `UserRepository` (and `AsyncUserRepository`) stub
+    /// for exercising `UserService` without a real backend.
+    #[derive(Default)]
+    struct InMemoryUserRepository {
+        users: RefCell<Vec<User>>,
+    }
+
+    impl InMemoryUserRepository {
+        fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    impl UserRepository for InMemoryUserRepository {
+        fn find_by_id(&self, id: &UserId) -> Option<User> {
+            self.users.borrow().iter().find(|u| &u.id == id).cloned()
+        }
+
+        fn find_by_email(&self, email: &str) -> Option<User> {
+            self.users
+                .borrow()
+                .iter()
+                .find(|u| u.email == email)
+                .cloned()
+        }
+
+        fn create(&self, user: &User) -> Result<(), String> {
+            if self.users.borrow().iter().any(|u| u.id == user.id) {
+                return Err("duplicate id".to_string());
+            }
+            self.users.borrow_mut().push(user.clone());
+            Ok(())
+        }
+
+        fn all(&self) -> Vec<User> {
+            self.users.borrow().clone()
+        }
+    }
+
+    #[allow(async_fn_in_trait)]
+    impl AsyncUserRepository for InMemoryUserRepository {
+        async fn find_by_id(&self, id: &UserId) -> Result<User, FetchError> {
+            UserRepository::find_by_id(self, id).ok_or(FetchError::NotFound)
+        }
+
+        async fn create(&self, user: &User) -> Result<(), FetchError> {
+            UserRepository::create(self, user).map_err(FetchError::Backend)
+        }
+    }
+
+    fn make_user(name: &str, email: &str, role: UserRole) -> User {
+        let mut user = create_user(name.to_string(), email.to_string());
+        user.role = role;
+        user
+    }
+
+    /// Polls a future to completion on the current thread. Futures in this
+    /// file complete on the first poll (no real I/O), so a no-op waker is
+    /// enough — this avoids pulling in an async runtime dependency just
+    /// for tests.
+    #[allow(dead_code)]
+    fn block_on<F: std::future::Future>(mut fut: F) -> F::Output {
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let raw_waker = RawWaker::new(std::ptr::null(), &VTABLE);
+        let waker = unsafe { Waker::from_raw(raw_waker) };
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { std::pin::Pin::new_unchecked(&mut fut) };
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(val) => return val,
+                Poll::Pending => continue,
+            }
+        }
+    }
+
+    fn hex_to_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sha512_matches_known_vectors() {
+        let empty_hash = concat!(
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce",
+            "47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        );
+        let abc_hash = concat!(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a",
+            "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(sha512(b"").to_vec(), hex_to_bytes(empty_hash));
+        assert_eq!(sha512(b"abc").to_vec(), hex_to_bytes(abc_hash));
+    }
+
+    #[test]
+    fn constant_time_eq_detects_equality_and_mismatch() {
+        let a = sha512(b"same");
+        let b = sha512(b"same");
+        let c = sha512(b"different");
+        assert!(constant_time_eq(&a, &b));
+        assert!(!constant_time_eq(&a, &c));
+    }
+
+    #[test]
+    fn sha1_matches_known_vectors() {
+        assert_eq!(
+            sha1(b"").to_vec(),
+            hex_to_bytes("da39a3ee5e6b4b0d3255bfef95601890afd80709")
+        );
+        assert_eq!(
+            sha1(b"abc").to_vec(),
+            hex_to_bytes("a9993e364706816aba3e25717850c26c9cd0d89d")
+        );
+    }
+
+    #[test]
+    fn uuid_v5_matches_known_vector() {
+        // RFC 4122 NAMESPACE_DNS, name "python.org" — a published test
+        // vector (also used by Python's `uuid.uuid5` doctest).
+        let namespace_dns = Uuid([
+            0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4,
+            0x30, 0xc8,
+        ]);
+        let expected = Uuid([
+            0x88, 0x63, 0x13, 0xe1, 0x3b, 0x8a, 0x53, 0x72, 0x9b, 0x90, 0x0c, 0x9a, 0xee, 0x19,
+            0x9e, 0x5d,
+        ]);
+        assert_eq!(Uuid::new_v5(&namespace_dns, b"python.org"), expected);
+    }
+
+    #[test]
+    fn stable_user_id_is_deterministic_and_case_insensitive() {
+        let a = stable_user_id("Alice");
+        let b = stable_user_id("alice");
+        let c = stable_user_id("bob");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn trigrams_are_lowercased_and_boundary_padded() {
+        let set = trigrams("Hi");
+        assert!(set.contains("  h"));
+        assert!(set.contains(" hi"));
+        assert!(set.contains("hi "));
+        assert!(!set.contains(" Hi"));
+    }
+
+    #[test]
+    fn trigram_similarity_is_one_for_identical_strings() {
+        let a = trigrams("alice");
+        let b = trigrams("alice");
+        assert_eq!(trigram_similarity(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn trigram_similarity_is_zero_for_disjoint_strings() {
+        let a = trigrams("alice");
+        let b = trigrams("zzzzz");
+        assert_eq!(trigram_similarity(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn trigram_similarity_rewards_close_matches_over_far_ones() {
+        let query = trigrams("alice");
+        let close = trigram_similarity(&query, &trigrams("alicc"));
+        let far = trigram_similarity(&query, &trigrams("zzzzz"));
+        assert!(close > far);
+    }
+
+    #[test]
+    fn policy_permissions_match_each_role() {
+        assert_eq!(
+            Policy::permissions_for(&UserRole::Admin),
+            HashSet::from([Permission::CreatePost, Permission::Comment, Permission::ManageUsers])
+        );
+        assert_eq!(
+            Policy::permissions_for(&UserRole::Member),
+            HashSet::from([Permission::CreatePost, Permission::Comment])
+        );
+        assert_eq!(Policy::permissions_for(&UserRole::Guest), HashSet::new());
+    }
+
+    #[test]
+    fn authorize_allows_member_but_denies_manage_users() {
+        let repo = InMemoryUserRepository::new();
+        let member = make_user("mallory", "mallory@example.com", UserRole::Member);
+        let id = member.id.clone();
+        UserRepository::create(&repo, &member).unwrap();
+        let service = UserService::new(repo);
+
+        assert!(service.authorize(&id, Permission::CreatePost).is_ok());
+        assert!(service.authorize(&id, Permission::Comment).is_ok());
+        assert_eq!(
+            service.authorize(&id, Permission::ManageUsers),
+            Err(AuthError::Denied)
+        );
+    }
+
+    #[test]
+    fn authorize_admin_has_manage_users() {
+        let repo = InMemoryUserRepository::new();
+        let admin = make_user("adelaide", "adelaide@example.com", UserRole::Admin);
+        let id = admin.id.clone();
+        UserRepository::create(&repo, &admin).unwrap();
+        let service = UserService::new(repo);
+
+        assert!(service.authorize(&id, Permission::ManageUsers).is_ok());
+    }
+
+    #[test]
+    fn authorize_guest_is_denied_everything() {
+        let repo = InMemoryUserRepository::new();
+        let guest = make_user("gus", "gus@example.com", UserRole::Guest);
+        let id = guest.id.clone();
+        UserRepository::create(&repo, &guest).unwrap();
+        let service = UserService::new(repo);
+
+        assert_eq!(
+            service.authorize(&id, Permission::Comment),
+            Err(AuthError::Denied)
+        );
+    }
+
+    #[test]
+    fn authorize_returns_not_found_for_unknown_user() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+        let unknown_id = stable_user_id("nobody");
+
+        assert_eq!(
+            service.authorize(&unknown_id, Permission::Comment),
+            Err(AuthError::NotFound)
+        );
+    }
+
+    #[test]
+    fn authorize_permission_override_bypasses_role_policy() {
+        let repo = InMemoryUserRepository::new();
+        let mut guest = make_user("gina", "gina@example.com", UserRole::Guest);
+        guest.permission_overrides.insert(Permission::ManageUsers);
+        let id = guest.id.clone();
+        UserRepository::create(&repo, &guest).unwrap();
+        let service = UserService::new(repo);
+
+        assert!(service.authorize(&id, Permission::ManageUsers).is_ok());
+    }
+
+    #[test]
+    fn authorize_resource_allows_owner_without_global_permission() {
+        let repo = InMemoryUserRepository::new();
+        let guest = make_user("owner", "owner@example.com", UserRole::Guest);
+        let id = guest.id.clone();
+        UserRepository::create(&repo, &guest).unwrap();
+        let service = UserService::new(repo);
+
+        assert!(service
+            .authorize_resource(&id, Permission::ManageUsers, "owner")
+            .is_ok());
+        assert_eq!(
+            service.authorize_resource(&id, Permission::ManageUsers, "someone-else"),
+            Err(AuthError::Denied)
+        );
+    }
+
+    #[test]
+    fn parse_users_toml_parses_valid_multi_user_file() {
+        let toml = r#"
+[[user]]
+name = "alice"
+email = "alice@example.com"
+role = "admin"
+
+[[user]]
+name = "bob"
+email = "bob@example.com"
+role = "member"
+permissions = ["ManageUsers"]
+"#;
+        let users = parse_users_toml(toml).expect("well-formed toml should parse");
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].name, "alice");
+        assert_eq!(users[0].role, UserRole::Admin);
+        assert_eq!(users[1].name, "bob");
+        assert_eq!(users[1].role, UserRole::Member);
+        assert!(users[1].permission_overrides.contains(&Permission::ManageUsers));
+    }
+
+    #[test]
+    fn parse_users_toml_rejects_missing_fields() {
+        let toml = "[[user]]\nname = \"alice\"\n";
+        let errors = parse_users_toml(toml).unwrap_err();
+        assert!(errors.contains(&ConfigError::MissingField { entry: 0, field: "email" }));
+    }
+
+    #[test]
+    fn parse_users_toml_rejects_unknown_role_and_permission() {
+        let toml = "[[user]]\nname = \"alice\"\nemail = \"alice@example.com\"\nrole = \"superuser\"\npermissions = [\"FlyToTheMoon\"]\n";
+        let errors = parse_users_toml(toml).unwrap_err();
+        assert!(errors.contains(&ConfigError::UnknownRole {
+            entry: 0,
+            role: "superuser".to_string()
+        }));
+        assert!(errors.contains(&ConfigError::UnknownPermission {
+            entry: 0,
+            permission: "FlyToTheMoon".to_string()
+        }));
+    }
+
+    #[test]
+    fn parse_users_toml_rejects_duplicate_emails() {
+        let toml = "[[user]]\nname = \"alice\"\nemail = \"dup@example.com\"\nrole = \"member\"\n\n[[user]]\nname = \"alicia\"\nemail = \"dup@example.com\"\nrole = \"member\"\n";
+        let errors = parse_users_toml(toml).unwrap_err();
+        assert!(errors.contains(&ConfigError::DuplicateEmail("dup@example.com".to_string())));
+    }
+
+    #[test]
+    fn parse_users_toml_aggregates_errors_across_entries_instead_of_short_circuiting() {
+        let toml = "[[user]]\nname = \"alice\"\n\n[[user]]\nemail = \"bob@example.com\"\n";
+        let errors = parse_users_toml(toml).unwrap_err();
+        assert!(errors.contains(&ConfigError::MissingField { entry: 0, field: "email" }));
+        assert!(errors.contains(&ConfigError::MissingField { entry: 1, field: "name" }));
+    }
+
+    #[test]
+    fn seed_from_config_reports_io_error_for_missing_file() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+
+        let errors = service
+            .seed_from_config("/nonexistent/path/cdx-does-not-exist.toml")
+            .unwrap_err();
+        assert!(matches!(errors.as_slice(), [ConfigError::Io(_)]));
+    }
+
+    #[test]
+    fn seed_from_config_reports_create_error_when_repository_rejects_entry() {
+        let repo = InMemoryUserRepository::new();
+        let existing = create_user("alice".to_string(), "existing@example.com".to_string());
+        UserRepository::create(&repo, &existing).unwrap();
+        let service = UserService::new(repo);
+
+        let path = std::env::temp_dir().join("cdx_seed_from_config_create_error.toml");
+        std::fs::write(
+            &path,
+            "[[user]]\nname = \"alice\"\nemail = \"alice@example.com\"\nrole = \"member\"\n",
+        )
+        .unwrap();
+        let result = service.seed_from_config(path.to_str().unwrap());
+        std::fs::remove_file(&path).ok();
+
+        let errors = result.unwrap_err();
+        assert!(matches!(errors.as_slice(), [ConfigError::Create(_)]));
+    }
+
+    #[test]
+    fn seed_from_config_creates_every_entry_on_success() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+
+        let path = std::env::temp_dir().join("cdx_seed_from_config_success.toml");
+        std::fs::write(
+            &path,
+            "[[user]]\nname = \"carol\"\nemail = \"carol@example.com\"\nrole = \"admin\"\n\n[[user]]\nname = \"dave\"\nemail = \"dave@example.com\"\nrole = \"guest\"\n",
+        )
+        .unwrap();
+        let result = service.seed_from_config(path.to_str().unwrap());
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_ok());
+        assert!(service.get_user(&stable_user_id("carol")).is_some());
+        assert!(service.get_user(&stable_user_id("dave")).is_some());
+    }
+
+    #[test]
+    fn fetch_user_delegates_to_async_repository_on_success() {
+        let repo = InMemoryUserRepository::new();
+        let alice = create_user("alice".to_string(), "alice@example.com".to_string());
+        let id = alice.id.clone();
+        UserRepository::create(&repo, &alice).unwrap();
+        let service = UserService::new(repo);
+
+        let result = block_on(service.fetch_user(&id));
+        assert_eq!(result.unwrap().name, "alice");
+    }
+
+    #[test]
+    fn fetch_user_returns_not_found_for_unknown_id() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+        let unknown_id = stable_user_id("nobody");
+
+        let result = block_on(service.fetch_user(&unknown_id));
+        assert_eq!(result.unwrap_err(), FetchError::NotFound);
+    }
+
+    #[test]
+    fn async_create_surfaces_backend_error_on_duplicate_id() {
+        let repo = InMemoryUserRepository::new();
+        let alice = create_user("alice".to_string(), "alice@example.com".to_string());
+        UserRepository::create(&repo, &alice).unwrap();
+        let service = UserService::new(repo);
+
+        let result = block_on(AsyncUserRepository::create(service_repository(&service), &alice));
+        assert_eq!(result, Err(FetchError::Backend("duplicate id".to_string())));
+    }
+
+    /// Test-only escape hatch to reach the private `repository` field for
+    /// the one test above that needs to call the async trait directly
+    /// rather than through a `UserService` method.
+    fn service_repository<R: UserRepository>(service: &UserService<R>) -> &R {
+        &service.repository
+    }
+
+    #[test]
+    fn register_then_verify_credentials_round_trips() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+        service
+            .register(
+                "trent".to_string(),
+                "trent@example.com".to_string(),
+                "correct horse battery staple",
+            )
+            .expect("registration should succeed");
+
+        assert_eq!(
+            service.verify_credentials("trent@example.com", "correct horse battery staple"),
+            Ok(true)
+        );
+        assert_eq!(
+            service.verify_credentials("trent@example.com", "wrong password"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn verify_credentials_fails_for_unknown_email() {
+        let repo = InMemoryUserRepository::new();
+        let service = UserService::new(repo);
+
+        assert!(service
+            .verify_credentials("nobody@example.com", "whatever")
+            .is_err());
+    }
+}